@@ -0,0 +1,418 @@
+/*
+  Copyright (C) 2021 Chinmay Dalal
+
+  This file is part of todo-cras.
+
+  todo-cras is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  todo-cras is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with todo-cras.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Conversion to and from the two external formats the wider todo ecosystem
+//! uses: todo.txt (projects, contexts, `due:` keys, `(A)` priority) and
+//! RFC-5545 `VTODO` calendar entries.
+
+use std::path::Path;
+use std::str;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use termcolor::Color;
+
+use crate::{msg, read, save, Category, Task};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    TodoTxt,
+    Ics,
+}
+
+impl str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_lowercase().as_str() {
+            "todotxt" | "todo.txt" => Ok(Self::TodoTxt),
+            "ics" | "ical" | "icalendar" => Ok(Self::Ics),
+            _ => Err(msg!("interop-invalid-format", format = format)),
+        }
+    }
+}
+
+/// # Errors
+/// Returns an error when
+/// 1) `file` can't be read, or its contents don't parse as `format`
+/// 2) `todo_file` has invalid syntax
+pub fn import(file: &Path, format: Format, todo_file: &Path) -> Result<(), String> {
+    let text = std::fs::read_to_string(file).map_err(|err| err.to_string())?;
+
+    let (imported_tasks, imported_categories) = match format {
+        Format::TodoTxt => parse_todotxt(&text),
+        Format::Ics => parse_ics(&text)?,
+    };
+
+    let (mut tasks, mut categories) = read(todo_file)?;
+    for category in imported_categories {
+        if !categories.iter().any(|existing| existing.name == category.name) {
+            categories.push(category);
+        }
+    }
+    tasks.extend(imported_tasks);
+
+    save(&categories, &tasks, todo_file);
+    Ok(())
+}
+
+/// # Errors
+/// Returns an error when
+/// 1) `todo_file` has invalid syntax
+/// 2) `file` can't be written
+pub fn export(format: Format, todo_file: &Path, file: &Path) -> Result<(), String> {
+    let (tasks, categories) = read(todo_file)?;
+
+    let text = match format {
+        Format::TodoTxt => to_todotxt(&categories, &tasks),
+        Format::Ics => to_ics(&tasks),
+    };
+
+    std::fs::write(file, text).map_err(|err| err.to_string())
+}
+
+fn unclassified() -> Category {
+    Category {
+        name: String::from("Unclassified"),
+        probability: 1.00,
+        color: Color::White,
+    }
+}
+
+// todo.txt
+
+fn parse_todotxt(text: &str) -> (Vec<Task>, Vec<Category>) {
+    let mut categories = vec![unclassified()];
+    let mut tasks = vec![];
+
+    for line in text.lines() {
+        let line = line.trim();
+        // Completed tasks have no counterpart in this crate's model; skip them.
+        if line.is_empty() || line.starts_with("x ") {
+            continue;
+        }
+
+        let (priority, rest) = strip_priority(line);
+        let mut category_name = None;
+        let mut deadline = None;
+        let mut words = Vec::new();
+
+        for word in rest.split_whitespace() {
+            if let Some(project) = word.strip_prefix('+') {
+                if category_name.is_none() {
+                    category_name = Some(String::from(project));
+                } else {
+                    // This crate only models one category per task; preserve
+                    // any further +project tags verbatim rather than
+                    // silently dropping them.
+                    words.push(word);
+                }
+            } else if let Some(due) = word.strip_prefix("due:") {
+                deadline = parse_todotxt_date(due);
+            } else if word.starts_with('@') {
+                // This crate has no dedicated context field; contexts are
+                // kept verbatim as part of the task name.
+                words.push(word);
+            } else {
+                words.push(word);
+            }
+        }
+
+        let category_name = category_name.unwrap_or_else(|| String::from("Unclassified"));
+        if !categories.iter().any(|category| category.name == category_name) {
+            categories.push(Category {
+                name: category_name.clone(),
+                probability: priority.map_or(1.0, priority_to_probability),
+                color: Color::White,
+            });
+        }
+
+        tasks.push(Task {
+            task: words.join(" "),
+            deadline,
+            category: category_name,
+            duration: None,
+        });
+    }
+
+    (tasks, categories)
+}
+
+/// Strips a leading `(A) ` priority marker, returning the priority letter and the rest.
+fn strip_priority(line: &str) -> (Option<char>, &str) {
+    let mut chars = line.chars();
+    if chars.next() == Some('(') {
+        if let (Some(priority), Some(')'), Some(' ')) = (chars.next(), chars.next(), chars.next()) {
+            if priority.is_ascii_uppercase() {
+                return (Some(priority), &line[4..]);
+            }
+        }
+    }
+    (None, line)
+}
+
+fn parse_todotxt_date(date: &str) -> Option<NaiveDateTime> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+}
+
+/// Maps todo.txt priority `(A)`..`(Z)` onto a category `probability`, `(A)` highest.
+///
+/// Kept strictly below `1.0` so a `(A)`-priority category stays distinguishable
+/// from a no-priority one (which keeps the default probability of `1.0`) on export.
+#[allow(clippy::cast_precision_loss)]
+fn priority_to_probability(priority: char) -> f32 {
+    let offset = (u32::from(priority) - u32::from('A')).min(24);
+    (0.96 - offset as f32 * 0.04).max(0.0)
+}
+
+/// The inverse of `priority_to_probability`; `None` for categories at full probability.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn probability_to_priority(probability: f32) -> Option<char> {
+    if probability >= 0.999 {
+        return None;
+    }
+    let offset = (((0.96 - probability) / 0.04).round() as i32).clamp(0, 24) as u32;
+    char::from_u32(u32::from('A') + offset)
+}
+
+fn to_todotxt(categories: &[Category], tasks: &[Task]) -> String {
+    let mut out = String::new();
+
+    for task in tasks {
+        let probability = categories
+            .iter()
+            .find(|category| category.name == task.category)
+            .map_or(1.0, |category| category.probability);
+
+        if let Some(priority) = probability_to_priority(probability) {
+            out.push_str(&format!("({}) ", priority));
+        }
+
+        out.push_str(&task.task);
+
+        if task.category != "Unclassified" {
+            out.push_str(&format!(" +{}", task.category.replace(' ', "_")));
+        }
+
+        if let Some(deadline) = task.deadline {
+            out.push_str(&format!(" due:{}", deadline.format("%Y-%m-%d")));
+        }
+
+        out.push('\n');
+    }
+
+    out
+}
+
+// iCalendar VTODO
+
+/// # Errors
+/// Returns an error when a `VTODO` block has no `SUMMARY`.
+fn parse_ics(text: &str) -> Result<(Vec<Task>, Vec<Category>), String> {
+    let mut categories = vec![unclassified()];
+    let mut tasks = vec![];
+
+    let mut in_vtodo = false;
+    let mut summary = None;
+    let mut due = None;
+    let mut category_name = None;
+    let mut completed = false;
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        if line == "BEGIN:VTODO" {
+            in_vtodo = true;
+            summary = None;
+            due = None;
+            category_name = None;
+            completed = false;
+        } else if line == "END:VTODO" {
+            in_vtodo = false;
+            if completed {
+                continue;
+            }
+            let summary = summary
+                .take()
+                .ok_or_else(|| msg!("interop-vtodo-missing-summary"))?;
+            let category_name = category_name
+                .take()
+                .unwrap_or_else(|| String::from("Unclassified"));
+
+            if !categories.iter().any(|category| category.name == category_name) {
+                categories.push(Category {
+                    name: category_name.clone(),
+                    probability: 1.0,
+                    color: Color::White,
+                });
+            }
+
+            tasks.push(Task {
+                task: summary,
+                deadline: due.take(),
+                category: category_name,
+                duration: None,
+            });
+        } else if in_vtodo {
+            if let Some(value) = line.strip_prefix("SUMMARY:") {
+                summary = Some(unescape_ics(value));
+            } else if let Some(value) = line.strip_prefix("DUE") {
+                due = parse_ics_date(value.trim_start_matches(|c| c != ':').trim_start_matches(':'));
+            } else if let Some(value) = line.strip_prefix("CATEGORIES:") {
+                category_name = value.split(',').next().map(unescape_ics);
+            } else if line.strip_prefix("STATUS:") == Some("COMPLETED") {
+                completed = true;
+            }
+        }
+    }
+
+    Ok((tasks, categories))
+}
+
+fn parse_ics_date(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y%m%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+fn to_ics(tasks: &[Task]) -> String {
+    let mut out = String::from("BEGIN:VCALENDAR\nVERSION:2.0\nPRODID:-//todo-cras//EN\n");
+
+    for task in tasks {
+        out.push_str("BEGIN:VTODO\n");
+        out.push_str(&format!("SUMMARY:{}\n", escape_ics(&task.task)));
+        if let Some(deadline) = task.deadline {
+            out.push_str(&format!("DUE:{}\n", deadline.format("%Y%m%dT%H%M%SZ")));
+        }
+        out.push_str(&format!("CATEGORIES:{}\n", escape_ics(&task.category)));
+        out.push_str("STATUS:NEEDS-ACTION\n");
+        out.push_str("END:VTODO\n");
+    }
+
+    out.push_str("END:VCALENDAR\n");
+    out
+}
+
+fn escape_ics(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(',', "\\,").replace(';', "\\;")
+}
+
+fn unescape_ics(value: &str) -> String {
+    value.replace("\\,", ",").replace("\\;", ";").replace("\\\\", "\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_ics, parse_todotxt, to_ics, to_todotxt};
+
+    #[test]
+    fn parse_todotxt_recognizes_priority_project_context_and_due() {
+        let (tasks, categories) = parse_todotxt("(A) Buy milk @store +Errands due:2024-01-15\n");
+
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].task, "Buy milk @store");
+        assert_eq!(tasks[0].category, "Errands");
+        assert_eq!(
+            tasks[0].deadline.map(|deadline| deadline.format("%Y-%m-%d").to_string()),
+            Some(String::from("2024-01-15"))
+        );
+
+        let category = categories
+            .iter()
+            .find(|category| category.name == "Errands")
+            .expect("Errands category should have been created");
+        assert!(category.probability < 1.0, "(A) priority should not collide with the no-priority default");
+    }
+
+    #[test]
+    fn parse_todotxt_preserves_extra_projects_instead_of_dropping_them() {
+        let (tasks, _) = parse_todotxt("Buy milk +Errands +Urgent\n");
+
+        assert_eq!(tasks[0].category, "Errands");
+        assert!(
+            tasks[0].task.contains("+Urgent"),
+            "a second +project tag should be preserved, not silently dropped: {:?}",
+            tasks[0].task
+        );
+    }
+
+    #[test]
+    fn parse_todotxt_skips_completed_tasks() {
+        let (tasks, _) = parse_todotxt("x Already done +Errands\nStill pending +Errands\n");
+
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].task, "Still pending");
+    }
+
+    #[test]
+    fn todotxt_round_trips_through_export_and_import() {
+        let (tasks, categories) = parse_todotxt("(A) Buy milk @store +Errands due:2024-01-15\n");
+
+        let exported = to_todotxt(&categories, &tasks);
+        let (reimported, _) = parse_todotxt(&exported);
+
+        assert_eq!(reimported.len(), 1);
+        assert_eq!(reimported[0].task, tasks[0].task);
+        assert_eq!(reimported[0].category, tasks[0].category);
+        assert_eq!(reimported[0].deadline, tasks[0].deadline);
+    }
+
+    #[test]
+    fn ics_round_trips_through_export_and_import() {
+        let (tasks, _) = parse_todotxt("Buy milk +Errands due:2024-01-15\n");
+
+        let exported = to_ics(&tasks);
+        let (reimported, _) = parse_ics(&exported).expect("round-tripped ICS should parse");
+
+        assert_eq!(reimported.len(), 1);
+        assert_eq!(reimported[0].task, tasks[0].task);
+        assert_eq!(reimported[0].category, tasks[0].category);
+        assert_eq!(reimported[0].deadline, tasks[0].deadline);
+    }
+
+    #[test]
+    fn parse_ics_skips_completed_vtodos() {
+        let text = "BEGIN:VCALENDAR\n\
+            BEGIN:VTODO\n\
+            SUMMARY:Done already\n\
+            STATUS:COMPLETED\n\
+            END:VTODO\n\
+            BEGIN:VTODO\n\
+            SUMMARY:Still pending\n\
+            END:VTODO\n\
+            END:VCALENDAR\n";
+
+        let (tasks, _) = parse_ics(text).expect("valid ICS should parse");
+
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].task, "Still pending");
+    }
+
+    #[test]
+    fn parse_ics_errors_on_missing_summary() {
+        let text = "BEGIN:VCALENDAR\nBEGIN:VTODO\nEND:VTODO\nEND:VCALENDAR\n";
+
+        assert!(parse_ics(text).is_err());
+    }
+}
@@ -28,17 +28,73 @@ use std::path::Path;
 use std::process::exit;
 use std::{io::prelude::*, str};
 
+use std::sync::OnceLock;
+
 use chrono::NaiveDateTime;
-use read_input::prelude::*;
 use regex::Regex;
-use termcolor::{Color, ColorChoice::Auto, ColorSpec, StandardStream, WriteColor};
-use termion::screen::AlternateScreen;
+use termcolor::{Buffer, Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+use terminal_size::{terminal_size, Height};
+
+mod locale;
+mod interop;
+mod schedule;
+mod tui;
+pub use interop::{export, import, Format};
+pub use locale::set_lang;
+pub use schedule::{schedule, DEFAULT_DURATION};
 
 const COLORS: [&str; 8] = [
     "Black", "Blue", "Green", "Red", "Cyan", "Magenta", "Yellow", "White",
 ];
 const FMT: &str = "%Y-%m-%d %H:%M";
 
+/// Global `--color` setting, parsed on the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Coloring {
+    Auto,
+    Always,
+    Never,
+}
+
+impl From<Coloring> for ColorChoice {
+    fn from(coloring: Coloring) -> Self {
+        match coloring {
+            Coloring::Auto => ColorChoice::Auto,
+            Coloring::Always => ColorChoice::Always,
+            Coloring::Never => ColorChoice::Never,
+        }
+    }
+}
+
+static COLOR_CHOICE: OnceLock<ColorChoice> = OnceLock::new();
+
+/// Configures the `ColorChoice` every colored write in this crate reads from.
+///
+/// Meant to be called once, early in `main`, with the parsed `--color` flag.
+pub fn set_coloring(coloring: Coloring) {
+    COLOR_CHOICE.set(coloring.into()).ok();
+}
+
+fn color_choice() -> ColorChoice {
+    COLOR_CHOICE.get().copied().unwrap_or(ColorChoice::Auto)
+}
+
+impl str::FromStr for Coloring {
+    type Err = String;
+
+    fn from_str(coloring: &str) -> Result<Self, Self::Err> {
+        match coloring.to_lowercase().as_str() {
+            "auto" => Ok(Coloring::Auto),
+            "always" => Ok(Coloring::Always),
+            "never" => Ok(Coloring::Never),
+            _ => Err(format!(
+                "Invalid color setting '{}', expected auto, always, or never",
+                coloring
+            )),
+        }
+    }
+}
+
 pub struct Category {
     name: String,
     probability: f32,
@@ -62,7 +118,7 @@ impl Category {
                 .unwrap()
                 .as_str(),
         )
-        .map_err(|err| format!("{} at\n{}", err, line))?;
+        .map_err(|err| msg!("parse-wrapped", error = err, line = line))?;
 
         let probability = regex
             .captures(line)
@@ -71,10 +127,10 @@ impl Category {
             .unwrap()
             .as_str()
             .parse::<f32>()
-            .map_err(|err| format!("Could not parse probability: {} at\n{}", err, line))?;
+            .map_err(|err| msg!("parse-invalid-probability", error = err.to_string(), line = line))?;
 
         if !(0.0..=1.0).contains(&probability) {
-            return Err(format!("Probability {} outside 0..=1", probability));
+            return Err(msg!("parse-probability-out-of-range", probability = probability));
         }
 
         Ok(Self {
@@ -83,96 +139,51 @@ impl Category {
             color,
         })
     }
-
-    fn edit(&mut self) {
-        color_print(Color::Yellow, &format!("Editing category '{}'", &self.name));
-
-        let operation = get_choices(&["Change name", "Change probability", "Change color"]);
-        match operation {
-            1 => {
-                clear();
-                self.name = input().msg("New name: ").get();
-            }
-            2 => {
-                clear();
-                self.probability = input::<f32>()
-                    .msg("New probability: ")
-                    .add_err_test(|x| (&0.0..=&1.0).contains(&x), "Invalid probability")
-                    .get();
-            }
-            3 => {
-                clear();
-                println!("New color: ");
-                self.color = parse_color(COLORS[get_choices(&COLORS.to_vec()) - 1]).unwrap();
-                clear();
-            }
-            _ => unreachable!(),
-        }
-    }
 }
 
 pub struct Task {
     task: String,
     deadline: Option<NaiveDateTime>,
     category: String,
+    duration: Option<u32>,
 }
 
 impl Task {
     fn parse(line: &str, category: &str) -> Result<Self, String> {
-        let regex =
-            Regex::new(r"^    Task name: (?P<name>[^\t]+)\tdeadline: (?P<deadline>.+)$").unwrap();
+        let regex = Regex::new(
+            r"^    Task name: (?P<name>[^\t]+)\tdeadline: (?P<deadline>[^\t]+)(\tduration: (?P<duration>.+))?$",
+        )
+        .unwrap();
 
-        let captured_deadline = regex
-            .captures(line)
-            .unwrap()
-            .name("deadline")
-            .unwrap()
-            .as_str()
-            .trim();
+        let captures = regex.captures(line).unwrap();
 
+        let captured_deadline = captures.name("deadline").unwrap().as_str().trim();
         let deadline = match captured_deadline {
             "none" => None,
             _ => Some(
                 NaiveDateTime::parse_from_str(captured_deadline, FMT)
-                    .map_err(|err| format!("Could not parse deadline: {} at\n{}", err, line))?,
+                    .map_err(|err| msg!("parse-invalid-deadline", error = err.to_string(), line = line))?,
             ),
         };
 
+        let duration = captures
+            .name("duration")
+            .map(|duration| {
+                duration
+                    .as_str()
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|err| msg!("parse-invalid-duration", error = err.to_string(), line = line))
+            })
+            .transpose()?;
+
         Ok(Self {
-            task: regex
-                .captures(line)
-                .unwrap()
-                .name("name")
-                .unwrap()
-                .as_str()
-                .to_string(),
+            task: captures.name("name").unwrap().as_str().to_string(),
             deadline,
             category: String::from(category),
+            duration,
         })
     }
-
-    fn edit(&mut self, categories: &[Category]) {
-        color_print(Color::Yellow, &format!("Editing task '{}'", &self.task));
-        let operation = get_choices(&["Change task name", "Change deadline", "Change category"]);
-        match operation {
-            1 => {
-                clear();
-                self.task = input().msg("New task name: ").get();
-            }
-            2 => {
-                clear();
-                self.deadline = get_deadline("New deadline: ");
-            }
-            3 => {
-                clear();
-                let category_names = get_category_names(categories);
-                let category_index = get_choices(&category_names);
-                clear();
-                self.category = String::from(category_names[category_index - 1]);
-            }
-            _ => unreachable!(),
-        }
-    }
 }
 
 /// # Errors
@@ -185,7 +196,8 @@ pub fn read(file: &Path) -> Result<(Vec<Task>, Vec<Category>), String> {
 
     let category_regex =
         Regex::new(r"^Category name: [^\t]+\tcolor: [^\t]+\tprobability: .+$").unwrap();
-    let task_regex = Regex::new(r"^    Task name: [^\t]+\tdeadline: .+$").unwrap();
+    let task_regex =
+        Regex::new(r"^    Task name: [^\t]+\tdeadline: [^\t]+(\tduration: .+)?$").unwrap();
 
     for line in text.lines() {
         if category_regex.is_match(line) {
@@ -193,11 +205,11 @@ pub fn read(file: &Path) -> Result<(Vec<Task>, Vec<Category>), String> {
         } else if task_regex.is_match(line) {
             tasks.push(Task::parse(line, &categories.last().unwrap().name)?);
         } else {
-            let mut color_stream = StandardStream::stdout(Auto);
+            let mut color_stream = StandardStream::stdout(color_choice());
             color_stream
                 .set_color(ColorSpec::new().set_fg(Some(Color::Red)))
                 .ok();
-            writeln!(color_stream, "Invalid format at {}", line).ok();
+            writeln!(color_stream, "{}", msg!("parse-invalid-line", line = line)).ok();
         }
     }
 
@@ -213,12 +225,7 @@ pub fn read(file: &Path) -> Result<(Vec<Task>, Vec<Category>), String> {
 }
 
 pub fn display(categories: &[Category], mut tasks: Vec<Task>, probability: bool) {
-    tasks.sort_by(|t1, t2| match (t1.deadline, t2.deadline) {
-        (Some(d1), Some(d2)) => d1.cmp(&d2),
-        (Some(_d1), None) => Ordering::Less,
-        (None, Some(_d2)) => Ordering::Greater,
-        (None, None) => Ordering::Equal,
-    });
+    sort_by_deadline(&mut tasks);
 
     let rand = {
         if probability {
@@ -228,7 +235,7 @@ pub fn display(categories: &[Category], mut tasks: Vec<Task>, probability: bool)
         }
     };
 
-    let mut color_stream = StandardStream::stdout(Auto);
+    let mut buffer = display_buffer();
 
     let mut has_task = HashSet::new();
     for task in &tasks {
@@ -239,25 +246,26 @@ pub fn display(categories: &[Category], mut tasks: Vec<Task>, probability: bool)
         .iter()
         .filter(|category| category.probability >= rand && has_task.contains(&category.name))
     {
-        color_stream
+        buffer
             .set_color(ColorSpec::new().set_fg(Some(category.color)))
             .ok();
-        writeln!(color_stream, "{}", category.name).ok();
+        writeln!(buffer, "{}", category.name).ok();
 
         for task in &tasks {
             if task.category == category.name {
                 let mut task_name_str = task.task.clone();
                 if let Some(deadline) = task.deadline {
                     if chrono::Local::now().naive_local() > deadline {
-                        task_name_str.push_str(" [BACKLOG]");
+                        task_name_str.push(' ');
+                        task_name_str.push_str(&msg!("display-backlog-marker"));
                     }
                 }
 
                 writeln!(
-                    color_stream,
+                    buffer,
                     "    {}: {}",
                     task.deadline
-                        .map_or(String::from("No deadline"), |deadline| deadline
+                        .map_or_else(|| msg!("display-no-deadline"), |deadline| deadline
                             .format(FMT)
                             .to_string()),
                     task_name_str
@@ -266,148 +274,111 @@ pub fn display(categories: &[Category], mut tasks: Vec<Task>, probability: bool)
             }
         }
     }
-}
 
-/// # Errors
-/// Returns errors when
-/// 1) File has invalid syntax
-/// 2) Alternate buffer can't be flushed
-pub fn edit_mode(file: &Path) -> Result<(), String> {
-    let mut screen = AlternateScreen::from(std::io::stdout());
-    clear();
-    let (mut tasks, mut categories) = read(file)?;
-    loop {
-        match get_choices(&["Category", "Task"]) {
-            1 => {
-                clear();
-                edit_categories(&mut categories, &mut tasks);
-            }
-            2 => {
-                clear();
-                edit_tasks(&mut tasks, &categories);
-            }
-            _ => unreachable!(),
-        }
-
-        let cont = input::<String>()
-            .msg("Continue editing? [y/n] ")
-            .add_err_test(
-                |str| str.as_str() == "y" || str.as_str() == "n",
-                "Please enter y or n",
-            )
-            .get();
-        if cont == "n" {
-            break;
-        }
-    }
-    save(&categories, &tasks, file);
-    screen.flush().map_err(|err| err.to_string())?;
-    Ok(())
+    present(buffer);
 }
 
-fn edit_categories(categories: &mut Vec<Category>, tasks: &mut [Task]) {
-    let category_names = get_category_names(categories);
-    match get_choices(&["Add category", "Edit category", "Delete category"]) {
-        1 => {
-            clear();
-            color_print(Color::Green, "Adding category");
-            let name = input::<String>().msg("Name: ").get();
-            let probability = input::<f32>()
-                .msg("Probability: ")
-                .add_err_test(|x| (&0.0..=&1.0).contains(&x), "Invalid probability")
-                .get();
-            println!("Color: ");
-            let color = parse_color(COLORS[get_choices(&COLORS.to_vec()) - 1]).unwrap();
-            clear();
-            categories.push(Category {
-                name,
-                probability,
-                color,
-            });
+/// Displays `tasks` in the order returned by `schedule`, i.e. the order that
+/// minimizes total weighted tardiness (or deadline order, above `schedule`'s
+/// task cap), alongside each task's planned start time.
+pub fn display_plan(categories: &[Category], tasks: Vec<Task>) {
+    let ordered = schedule(tasks, categories);
+
+    let mut buffer = display_buffer();
+    let mut elapsed = 0_u32;
+    for task in &ordered {
+        if let Some(category) = categories.iter().find(|category| category.name == task.category) {
+            buffer
+                .set_color(ColorSpec::new().set_fg(Some(category.color)))
+                .ok();
         }
+        writeln!(
+            buffer,
+            "+{:>4} min  {}: {}",
+            elapsed, task.category, task.task
+        )
+        .ok();
+        elapsed += task.duration.unwrap_or(DEFAULT_DURATION);
+    }
 
-        2 => {
-            clear();
-            let category = get_choices(&category_names);
-            clear();
-            categories[category - 1].edit();
-        }
+    present(buffer);
+}
 
-        3 => {
-            clear();
-            let category_index = get_choices(&category_names) - 1;
-            let category_name = category_names[category_index];
-
-            if category_name == "Unclassified" {
-                let mut red_stream = StandardStream::stdout(Auto);
-                red_stream
-                    .set_color(ColorSpec::new().set_fg(Some(Color::Red)))
-                    .ok();
-                writeln!(red_stream, "Cannot delete special category Unclassified").ok();
-                exit(1);
-            }
+/// Sorts `tasks` by deadline ascending, with tasks that have none sorted last.
+pub(crate) fn sort_by_deadline(tasks: &mut [Task]) {
+    tasks.sort_by(|t1, t2| match (t1.deadline, t2.deadline) {
+        (Some(d1), Some(d2)) => d1.cmp(&d2),
+        (Some(_d1), None) => Ordering::Less,
+        (None, Some(_d2)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    });
+}
 
-            clear();
-            color_print(Color::Red, &format!("Removing category '{}'. All tasks in this category will be moved to 'Unclassified'", category_name));
+/// Writes `buffer` straight to stdout, or through a pager if it won't fit on screen.
+fn present(buffer: Buffer) {
+    let line_count = buffer.as_slice().iter().filter(|&&byte| byte == b'\n').count();
+    let fits_on_screen = terminal_size().map_or(true, |(_, Height(height))| {
+        line_count <= height as usize
+    });
 
-            for task in tasks.iter_mut() {
-                if task.category == category_name {
-                    task.category = String::from("Unclassified");
-                }
-            }
+    if fits_on_screen || !atty::is(atty::Stream::Stdout) {
+        std::io::stdout().write_all(buffer.as_slice()).ok();
+    } else {
+        page(buffer.as_slice());
+    }
+}
 
-            if !category_names.iter().any(|v| v == &"Unclassified") {
-                categories.push(Category {
-                    name: String::from("Unclassified"),
-                    probability: 1.00,
-                    color: Color::White,
-                });
+/// Builds a `termcolor::Buffer` that respects the configured `--color` setting.
+fn display_buffer() -> Buffer {
+    match color_choice() {
+        ColorChoice::Never => Buffer::no_color(),
+        ColorChoice::Always | ColorChoice::AlwaysAnsi => Buffer::ansi(),
+        ColorChoice::Auto => {
+            if atty::is(atty::Stream::Stdout) {
+                Buffer::ansi()
+            } else {
+                Buffer::no_color()
             }
-            categories.remove(category_index);
         }
-        _ => unreachable!(),
-    };
+    }
 }
 
-fn edit_tasks(tasks: &mut Vec<Task>, categories: &[Category]) {
-    match get_choices(&["Add task", "Edit task", "Delete task"]) {
-        1 => {
-            clear();
-            let category_number = get_choices(&get_category_names(categories));
-            clear();
-            let category = String::from(get_category_names(categories)[category_number - 1]);
-            color_print(
-                Color::Green,
-                &format!("Adding task to category '{}'", category),
-            );
-            let task = input::<String>().msg("Task name: ").get();
-            let deadline = get_deadline("Deadline: ");
-            tasks.push(Task {
-                task,
-                deadline,
-                category,
-            });
-        }
+/// Writes already-colored `content` to `$PAGER` (falling back to `less -R`), so that
+/// long, multi-category task lists are scrollable instead of spilling past the screen.
+fn page(content: &[u8]) {
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| String::from("less -R"));
+    let mut parts = pager.split_whitespace();
+
+    let piped = parts.next().and_then(|cmd| {
+        std::process::Command::new(cmd)
+            .args(parts)
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .ok()
+    });
 
-        2 => {
-            clear();
-            let task_names = get_task_names(tasks);
-            let task_index = get_choices(&task_names) - 1;
-            clear();
-            tasks[task_index].edit(categories);
+    match piped {
+        Some(mut child) => {
+            if let Some(stdin) = child.stdin.as_mut() {
+                stdin.write_all(content).ok();
+            }
+            child.wait().ok();
         }
-
-        3 => {
-            clear();
-            color_print(Color::Red, "Deleting the task you choose");
-            let task_names = get_task_names(tasks);
-            let task_index = get_choices(&task_names) - 1;
-            clear();
-            tasks.remove(task_index);
+        None => {
+            std::io::stdout().write_all(content).ok();
         }
+    }
+}
 
-        _ => unreachable!(),
-    };
+/// # Errors
+/// Returns errors when
+/// 1) The file has invalid syntax
+/// 2) The terminal can't be put into raw/alternate-screen mode, or a write to it fails
+pub fn edit_mode(file: &Path) -> Result<(), String> {
+    let (mut tasks, mut categories) = read(file)?;
+    tui::run(&mut tasks, &mut categories).map_err(|err| err.to_string())?;
+    save(&categories, &tasks, file);
+    Ok(())
 }
 
 /// # Panics
@@ -423,7 +394,7 @@ fn save(categories: &[Category], tasks: &[Task], file: &Path) {
         .ok();
         for task in tasks {
             if task.category == category.name {
-                writeln!(
+                write!(
                     out,
                     "    Task name: {}\tdeadline: {}",
                     task.task,
@@ -433,32 +404,80 @@ fn save(categories: &[Category], tasks: &[Task], file: &Path) {
                     )
                 )
                 .ok();
+                if let Some(duration) = task.duration {
+                    write!(out, "\tduration: {}", duration).ok();
+                }
+                writeln!(out).ok();
             }
         }
     }
 }
 
-pub fn help() {
-    let help = r"Usage:
-    todo_cras <no arguments>: Display all tasks
-              -p:             Display tasks according to probability
-              -e:             Edit tasks and categories
-              -h:             Display this help";
-    println!("{}", help);
+/// # Errors
+/// Returns an error when
+/// 1) The file has invalid syntax
+/// 2) `category` doesn't name an existing category
+/// 3) `deadline` is given and can't be parsed (an empty string included)
+/// 4) `duration` is given and can't be parsed (an empty string included)
+pub fn add_task(
+    file: &Path,
+    name: String,
+    deadline: Option<String>,
+    category: Option<String>,
+    duration: Option<String>,
+) -> Result<(), String> {
+    let (mut tasks, categories) = read(file)?;
+
+    let category = category.unwrap_or_else(|| String::from("Unclassified"));
+    if !categories.iter().any(|c| c.name == category) {
+        return Err(msg!("task-no-such-category", name = category));
+    }
+
+    let deadline = deadline
+        .map(|deadline| {
+            NaiveDateTime::parse_from_str(deadline.trim(), FMT)
+                .map_err(|err| msg!("task-invalid-deadline", error = err.to_string()))
+        })
+        .transpose()?;
+
+    let duration = duration
+        .map(|duration| {
+            duration
+                .trim()
+                .parse::<u32>()
+                .map_err(|err| msg!("task-invalid-duration", error = err.to_string()))
+        })
+        .transpose()?;
+
+    tasks.push(Task {
+        task: name,
+        deadline,
+        category,
+        duration,
+    });
+    save(&categories, &tasks, file);
+    Ok(())
 }
 
-// Helpers
+/// # Errors
+/// Returns an error when
+/// 1) The file has invalid syntax
+/// 2) No task named `task_name` exists
+pub fn mark_done(file: &Path, task_name: &str) -> Result<(), String> {
+    let (mut tasks, categories) = read(file)?;
 
-fn get_choices(choices: &[&str]) -> usize {
-    for (iteration, choice) in choices.iter().enumerate() {
-        println!("{}: {}", iteration + 1, choice);
-    }
-    input::<usize>()
-        .msg(format!("Your choice [{}-{}]: ", 1, choices.len()))
-        .inside_err(1..=choices.len(), "Invalid choice")
-        .get()
+    let index = tasks
+        .iter()
+        .position(|task| task.task == task_name)
+        .ok_or_else(|| msg!("task-no-such-task", name = task_name))?;
+    tasks.remove(index);
+
+    save(&categories, &tasks, file);
+    Ok(())
 }
 
+// Helpers
+
 fn parse_color(color: &str) -> Result<Color, String> {
     match color.to_lowercase().as_str() {
         "black" => Ok(Color::Black),
@@ -469,48 +488,8 @@ fn parse_color(color: &str) -> Result<Color, String> {
         "magenta" => Ok(Color::Magenta),
         "yellow" => Ok(Color::Yellow),
         "white" => Ok(Color::White),
-        _ => Err(format!("Invalid color {}", color)),
-    }
-}
-
-fn get_category_names(categories: &[Category]) -> Vec<&str> {
-    let mut v: Vec<&str> = Vec::with_capacity(categories.len());
-    for category in categories.iter() {
-        v.push(category.name.as_str());
-    }
-    v
-}
-
-fn get_task_names(tasks: &[Task]) -> Vec<&str> {
-    let mut v: Vec<&str> = Vec::with_capacity(tasks.len());
-    for category in tasks.iter() {
-        v.push(category.task.as_str());
+        _ => Err(msg!("invalid-color", color = color)),
     }
-    v
-}
-
-fn get_deadline(msg: &str) -> Option<NaiveDateTime> {
-    let input = input::<String>()
-        .msg(msg)
-        .add_err_test(
-            |x| NaiveDateTime::parse_from_str(x.as_str().trim(), FMT).is_ok() || x.trim() == "",
-            "Invalid deadline",
-        )
-        .get();
-
-    if input.as_str().trim() == "" {
-        None
-    } else {
-        Some(NaiveDateTime::parse_from_str(input.as_str(), FMT).unwrap())
-    }
-}
-
-fn clear() {
-    assert!(std::process::Command::new("cls")
-        .status()
-        .or_else(|_| std::process::Command::new("clear").status())
-        .unwrap()
-        .success());
 }
 
 pub trait HandleErr {
@@ -529,7 +508,7 @@ impl<T, E: Display> HandleErr for Result<T, E> {
 }
 
 fn color_print(color: Color, text: &str) {
-    let mut color_stream = StandardStream::stdout(Auto);
+    let mut color_stream = StandardStream::stdout(color_choice());
     color_stream
         .set_color(ColorSpec::new().set_fg(Some(color)))
         .ok();
@@ -0,0 +1,106 @@
+/*
+  Copyright (C) 2021 Chinmay Dalal
+
+  This file is part of todo-cras.
+
+  todo-cras is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  todo-cras is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with todo-cras.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Loads the Fluent catalogs embedded from `locales/`, selects one at runtime
+//! via `--lang` (falling back to `$LANG`, then `en`), and falls back to the
+//! English catalog for any key the selected locale doesn't define.
+
+use std::sync::OnceLock;
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::FluentResource;
+pub use fluent_bundle::{FluentArgs, FluentValue};
+use unic_langid::LanguageIdentifier;
+
+const EN_FTL: &str = include_str!("../locales/en.ftl");
+const ES_FTL: &str = include_str!("../locales/es.ftl");
+
+static LANG: OnceLock<String> = OnceLock::new();
+static BUNDLES: OnceLock<(FluentBundle<FluentResource>, FluentBundle<FluentResource>)> =
+    OnceLock::new();
+
+/// Configures the locale every `msg!` lookup reads from.
+///
+/// Meant to be called once, early in `main`, with the parsed `--lang` flag.
+pub fn set_lang(lang: Option<String>) {
+    let lang = lang
+        .or_else(|| std::env::var("LANG").ok())
+        .unwrap_or_else(|| String::from("en"));
+    let lang = lang.split(['_', '.']).next().unwrap_or("en").to_lowercase();
+    LANG.set(lang).ok();
+}
+
+fn catalog_for(lang: &str) -> &'static str {
+    match lang {
+        "es" => ES_FTL,
+        _ => EN_FTL,
+    }
+}
+
+fn build_bundle(source: &str) -> FluentBundle<FluentResource> {
+    let resource = FluentResource::try_new(source.to_string()).expect("invalid Fluent syntax");
+    let mut bundle = FluentBundle::new(vec!["en".parse::<LanguageIdentifier>().unwrap()]);
+    bundle.add_resource(resource).expect("duplicate Fluent message");
+    bundle
+}
+
+fn bundles() -> &'static (FluentBundle<FluentResource>, FluentBundle<FluentResource>) {
+    BUNDLES.get_or_init(|| {
+        let lang = LANG.get().cloned().unwrap_or_else(|| String::from("en"));
+        (build_bundle(catalog_for(&lang)), build_bundle(EN_FTL))
+    })
+}
+
+/// Looks up `key` in the selected locale, falling back to English if it's
+/// missing there. Panics only if English itself lacks `key`, or the message
+/// found for it has no value, which is a catalog bug rather than something a user or a bad `--lang` can trigger.
+#[doc(hidden)]
+pub fn message(key: &str, args: Option<&FluentArgs>) -> String {
+    let (primary, fallback) = bundles();
+    let (bundle, message) = primary
+        .get_message(key)
+        .map(|message| (primary, message))
+        .or_else(|| fallback.get_message(key).map(|message| (fallback, message)))
+        .unwrap_or_else(|| panic!("Missing Fluent key '{}'", key));
+
+    let pattern = message
+        .value()
+        .unwrap_or_else(|| panic!("Fluent key '{}' has no value", key));
+
+    let mut errors = vec![];
+    bundle.format_pattern(pattern, args, &mut errors).into_owned()
+}
+
+/// Looks up a localized message by key, interpolating any `name = value` pairs.
+///
+/// Each `value` must implement `Into<FluentValue>`; numbers are passed through
+/// as `FluentValue::Number` so Fluent's plural-category selectors can key off
+/// them, rather than being flattened to strings. Pass `Display`-only values
+/// (e.g. parse errors) as `value.to_string()` at the call site.
+#[macro_export]
+macro_rules! msg {
+    ($key:expr) => {
+        $crate::locale::message($key, None)
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = $crate::locale::FluentArgs::new();
+        $(args.set(stringify!($name), $crate::locale::FluentValue::from($value));)+
+        $crate::locale::message($key, Some(&args))
+    }};
+}
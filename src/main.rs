@@ -18,37 +18,146 @@
 */
 
 use std::path::PathBuf;
-use todo_cras::{display, edit_mode, help, read, HandleErr};
+use todo_cras::{
+    add_task, display, display_plan, edit_mode, export, import, mark_done, read, set_coloring,
+    set_lang, Coloring, Format, HandleErr,
+};
 
+use clap::{Parser, Subcommand};
 use home::home_dir;
 
+#[derive(Parser)]
+#[command(name = "todo-cras", version, about = "A todo list with categories and deadlines")]
+struct Cli {
+    /// Path to the todo file, overrides TODO_FILE and ~/todo.txt
+    #[arg(long, global = true)]
+    file: Option<PathBuf>,
+
+    /// Whether to color output: auto, always, or never
+    #[arg(long, global = true, default_value = "auto")]
+    color: Coloring,
+
+    /// Locale for prompts and messages, overrides $LANG (falls back to English)
+    #[arg(long, global = true)]
+    lang: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Display all tasks (default when no subcommand is given)
+    Show {
+        /// Hide some categories at random, weighted by their probability
+        #[arg(long)]
+        probability: bool,
+    },
+
+    /// Interactively edit tasks and categories
+    Edit,
+
+    /// Add a new task
+    Add {
+        /// Name of the task
+        #[arg(long)]
+        name: String,
+
+        /// Deadline, in "%Y-%m-%d %H:%M" format
+        #[arg(long)]
+        deadline: Option<String>,
+
+        /// Category to add the task to, defaults to "Unclassified"
+        #[arg(long)]
+        category: Option<String>,
+
+        /// Expected duration, in minutes
+        #[arg(long)]
+        duration: Option<String>,
+    },
+
+    /// Mark a task as done, removing it from the list
+    Done {
+        /// Name of the task to remove
+        task: String,
+    },
+
+    /// Show tasks in the order that minimizes total weighted tardiness
+    Plan,
+
+    /// Import tasks from a todo.txt or iCalendar VTODO file
+    Import {
+        /// Format of the file being imported: todotxt or ics
+        #[arg(long)]
+        format: Format,
+
+        /// Path to the file to import
+        file: PathBuf,
+    },
+
+    /// Export tasks to a todo.txt or iCalendar VTODO file
+    Export {
+        /// Format to export to: todotxt or ics
+        #[arg(long)]
+        format: Format,
+
+        /// Path to the file to write
+        file: PathBuf,
+    },
+}
+
 fn main() {
-    let mut args = std::env::args();
-    args.next();
-
-    let file = std::env::var_os("TODO_FILE")
-        .map_or_else(|| home_dir().unwrap().join("todo.txt"), PathBuf::from);
-
-    if let Some(arg) = args.next() {
-        match arg.as_str() {
-            // Edit mode.
-            "-e" => {
-                edit_mode(&file).ok_or_exit();
-            }
-
-            // Display mode, with probability. Useful as shell greeting.
-            "-p" => {
-                let (tasks, categories) = read(&file).ok_or_exit();
-                display(&categories, tasks, true);
-            }
-
-            // Display help if unrecognised arguments are given.
-            _ => help(),
-        };
-    } else {
-        // Display mode, without probability. Useful as command.
+    let cli = Cli::parse();
+    set_coloring(cli.color);
+    set_lang(cli.lang);
+
+    let file = cli.file.unwrap_or_else(|| {
+        std::env::var_os("TODO_FILE")
+            .map_or_else(|| home_dir().unwrap().join("todo.txt"), PathBuf::from)
+    });
+
+    match cli.command {
+        // Edit mode.
+        Some(Command::Edit) => {
+            edit_mode(&file).ok_or_exit();
+        }
 
-        let (tasks, categories) = read(&file).ok_or_exit();
-        display(&categories, tasks, false);
+        // Display mode, optionally with probability. Useful as shell greeting.
+        Some(Command::Show { probability }) => {
+            let (tasks, categories) = read(&file).ok_or_exit();
+            display(&categories, tasks, probability);
+        }
+
+        Some(Command::Add {
+            name,
+            deadline,
+            category,
+            duration,
+        }) => {
+            add_task(&file, name, deadline, category, duration).ok_or_exit();
+        }
+
+        Some(Command::Done { task }) => {
+            mark_done(&file, &task).ok_or_exit();
+        }
+
+        Some(Command::Plan) => {
+            let (tasks, categories) = read(&file).ok_or_exit();
+            display_plan(&categories, tasks);
+        }
+
+        Some(Command::Import { format, file: from }) => {
+            import(&from, format, &file).ok_or_exit();
+        }
+
+        Some(Command::Export { format, file: to }) => {
+            export(format, &file, &to).ok_or_exit();
+        }
+
+        // Display mode, without probability. Useful as command.
+        None => {
+            let (tasks, categories) = read(&file).ok_or_exit();
+            display(&categories, tasks, false);
+        }
     }
 }
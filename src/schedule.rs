@@ -0,0 +1,176 @@
+/*
+  Copyright (C) 2021 Chinmay Dalal
+
+  This file is part of todo-cras.
+
+  todo-cras is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  todo-cras is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with todo-cras.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Computes an execution order that minimizes total weighted tardiness,
+//! instead of the naive earliest-deadline sort used elsewhere.
+
+use crate::{sort_by_deadline, Category, Task};
+
+/// Duration, in minutes, assigned to a task that doesn't record one.
+pub const DEFAULT_DURATION: u32 = 30;
+
+/// `schedule` runs an exact `O(2^n * n)` DP, so cap `n` here and fall back to
+/// the EDD sort above this.
+const MAX_TASKS: usize = 20;
+
+/// Returns `tasks` reordered to minimize total weighted tardiness.
+///
+/// Treats this as single-machine scheduling: `dp[S]` is the minimum total
+/// weighted tardiness achievable after completing exactly the tasks in subset
+/// `S`, with the completion time of the last-completed task equal to the sum
+/// of durations in `S`. Transitioning from `S` to `S + {j}` adds
+/// `weight_j * max(0, completion_time(S + {j}) - deadline_j)`, where a task's
+/// weight is its category's `probability` and tasks with no deadline never
+/// contribute tardiness. The optimal order is recovered by backtracking the
+/// argmin transition into each subset.
+///
+/// For `tasks.len() > MAX_TASKS` the DP is infeasible, so this falls back to
+/// sorting by deadline (EDD), same as `display`.
+#[must_use]
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+pub fn schedule(mut tasks: Vec<Task>, categories: &[Category]) -> Vec<Task> {
+    let n = tasks.len();
+    if n > MAX_TASKS {
+        sort_by_deadline(&mut tasks);
+        return tasks;
+    }
+
+    let now = chrono::Local::now().naive_local();
+    let durations: Vec<u32> = tasks
+        .iter()
+        .map(|task| task.duration.unwrap_or(DEFAULT_DURATION))
+        .collect();
+    let weights: Vec<f64> = tasks
+        .iter()
+        .map(|task| {
+            categories
+                .iter()
+                .find(|category| category.name == task.category)
+                .map_or(1.0, |category| f64::from(category.probability))
+        })
+        .collect();
+    // Minutes from `now` until each task's deadline; `None` never incurs tardiness.
+    let deadlines: Vec<Option<i64>> = tasks
+        .iter()
+        .map(|task| task.deadline.map(|deadline| (deadline - now).num_minutes()))
+        .collect();
+
+    let subsets = 1_usize << n;
+    let mut best_tardiness = vec![f64::INFINITY; subsets];
+    let mut completion_time = vec![0_u32; subsets];
+    let mut predecessor = vec![None; subsets];
+    best_tardiness[0] = 0.0;
+
+    for set in 0..subsets {
+        if best_tardiness[set].is_infinite() {
+            continue;
+        }
+        for task in 0..n {
+            if set & (1 << task) != 0 {
+                continue;
+            }
+
+            let next_set = set | (1 << task);
+            let completion = completion_time[set] + durations[task];
+            let tardiness = deadlines[task].map_or(0.0, |deadline| {
+                weights[task] * (f64::from(completion) - deadline as f64).max(0.0)
+            });
+            let total_tardiness = best_tardiness[set] + tardiness;
+
+            if total_tardiness < best_tardiness[next_set] {
+                best_tardiness[next_set] = total_tardiness;
+                completion_time[next_set] = completion;
+                predecessor[next_set] = Some((set, task));
+            }
+        }
+    }
+
+    let mut order = Vec::with_capacity(n);
+    let mut set = subsets - 1;
+    while let Some((previous_set, task)) = predecessor[set] {
+        order.push(task);
+        set = previous_set;
+    }
+    order.reverse();
+
+    let mut tasks: Vec<Option<Task>> = tasks.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .map(|index| tasks[index].take().unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{schedule, Category, Task};
+    use termcolor::Color;
+
+    fn category(name: &str, probability: f32) -> Category {
+        Category {
+            name: String::from(name),
+            probability,
+            color: Color::White,
+        }
+    }
+
+    fn task(name: &str, category: &str, minutes_from_now: i64, duration: u32) -> Task {
+        Task {
+            task: String::from(name),
+            deadline: Some(chrono::Local::now().naive_local() + chrono::Duration::minutes(minutes_from_now)),
+            category: String::from(category),
+            duration: Some(duration),
+        }
+    }
+
+    /// With every task weighted equally and taking the same duration,
+    /// minimizing weighted tardiness reduces to minimizing plain tardiness, for
+    /// which EDD (earliest deadline first) is optimal: no task should be
+    /// reordered ahead of one with an earlier deadline.
+    #[test]
+    fn matches_edd_order_when_weights_are_equal() {
+        let categories = vec![category("Unclassified", 1.0)];
+        let tasks = vec![
+            task("third", "Unclassified", 30, 10),
+            task("first", "Unclassified", 10, 10),
+            task("second", "Unclassified", 20, 10),
+        ];
+
+        let scheduled = schedule(tasks, &categories);
+        let names: Vec<&str> = scheduled.iter().map(|task| task.task.as_str()).collect();
+        assert_eq!(names, ["first", "second", "third"]);
+    }
+
+    /// A low-weight task with an earlier, tight deadline and a high-weight task
+    /// with a slightly later, equally tight deadline: running the high-weight
+    /// task first costs the low-weight task a small tardiness penalty, while
+    /// EDD order (early-deadline first) would cost the high-weight task a huge
+    /// one. The DP should pick the cheaper order even though it contradicts EDD.
+    #[test]
+    fn prioritizes_higher_weight_task_despite_later_deadline() {
+        let categories = vec![category("Low", 1.0), category("High", 1000.0)];
+        let tasks = vec![
+            task("low-weight-early-deadline", "Low", 5, 5),
+            task("high-weight-late-deadline", "High", 6, 5),
+        ];
+
+        let scheduled = schedule(tasks, &categories);
+        let names: Vec<&str> = scheduled.iter().map(|task| task.task.as_str()).collect();
+        assert_eq!(names, ["high-weight-late-deadline", "low-weight-early-deadline"]);
+    }
+}
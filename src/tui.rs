@@ -0,0 +1,435 @@
+/*
+  Copyright (C) 2021 Chinmay Dalal
+
+  This file is part of todo-cras.
+
+  todo-cras is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  todo-cras is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with todo-cras.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! A full-screen editor for categories and tasks: an `AlternateScreen` panel
+//! driven by single keypresses, with fields edited inline on the status row
+//! rather than through numbered prompts and a clear-and-re-prompt loop.
+
+use std::collections::HashSet;
+use std::io::{self, Stdin, Write};
+
+use termion::cursor::Goto;
+use termion::event::Key;
+use termion::input::{Keys, TermRead};
+use termion::raw::IntoRawMode;
+use termion::screen::AlternateScreen;
+use termion::{clear, color, style};
+
+use termcolor::Color;
+
+use crate::{msg, Category, Task, COLORS, FMT};
+
+/// A row in the flattened category/task tree the user navigates.
+enum Row {
+    Category(usize),
+    Task(usize),
+}
+
+/// Runs the editor until the user quits, mutating `tasks` and `categories` in place.
+///
+/// # Errors
+/// Returns an error if the terminal can't be put into raw/alternate-screen
+/// mode, or if a write to it fails.
+pub fn run(tasks: &mut Vec<Task>, categories: &mut Vec<Category>) -> io::Result<()> {
+    let mut screen = AlternateScreen::from(io::stdout().into_raw_mode()?);
+    let mut keys = io::stdin().keys();
+
+    write!(screen, "{}", termion::cursor::Hide)?;
+
+    let mut expanded: HashSet<usize> = (0..categories.len()).collect();
+    let mut selected = 0_usize;
+    let mut message: Option<String> = None;
+
+    loop {
+        let rows = build_rows(categories, tasks, &expanded);
+        selected = if rows.is_empty() {
+            0
+        } else {
+            selected.min(rows.len() - 1)
+        };
+
+        draw(&mut screen, categories, tasks, &expanded, selected, message.take())?;
+
+        let Some(key) = keys.next() else { break };
+        match key? {
+            Key::Char('q') | Key::Esc => break,
+
+            Key::Up | Key::Char('k') => selected = selected.saturating_sub(1),
+            Key::Down | Key::Char('j') => {
+                if selected + 1 < rows.len() {
+                    selected += 1;
+                }
+            }
+            Key::Right | Key::Char('l') => {
+                if let Some(Row::Category(index)) = rows.get(selected) {
+                    expanded.insert(*index);
+                }
+            }
+            Key::Left | Key::Char('h') => {
+                if let Some(Row::Category(index)) = rows.get(selected) {
+                    expanded.remove(index);
+                }
+            }
+
+            Key::Char('A') => {
+                if let Some(name) =
+                    read_line(&mut screen, &mut keys, &msg!("tui-new-category-name"), "")?
+                {
+                    if !name.trim().is_empty() {
+                        categories.push(Category {
+                            name,
+                            probability: 1.0,
+                            color: Color::White,
+                        });
+                        expanded.insert(categories.len() - 1);
+                    }
+                }
+            }
+            Key::Char('a') => {
+                if let Some(category_index) = category_of(&rows, selected, categories, tasks) {
+                    if let Some(name) =
+                        read_line(&mut screen, &mut keys, &msg!("tui-new-task-name"), "")?
+                    {
+                        if !name.trim().is_empty() {
+                            tasks.push(Task {
+                                task: name,
+                                deadline: None,
+                                category: categories[category_index].name.clone(),
+                                duration: None,
+                            });
+                            expanded.insert(category_index);
+                        }
+                    }
+                }
+            }
+
+            Key::Char('n') => match rows.get(selected) {
+                Some(Row::Category(index)) => {
+                    if let Some(name) = read_line(
+                        &mut screen,
+                        &mut keys,
+                        &msg!("tui-rename-category"),
+                        &categories[*index].name,
+                    )? {
+                        if !name.trim().is_empty() {
+                            categories[*index].name = name;
+                        }
+                    }
+                }
+                Some(Row::Task(index)) => {
+                    if let Some(name) = read_line(
+                        &mut screen,
+                        &mut keys,
+                        &msg!("tui-rename-task"),
+                        &tasks[*index].task,
+                    )? {
+                        if !name.trim().is_empty() {
+                            tasks[*index].task = name;
+                        }
+                    }
+                }
+                None => {}
+            },
+
+            Key::Char('p') => {
+                if let Some(Row::Category(index)) = rows.get(selected) {
+                    let initial = format!("{:.2}", categories[*index].probability);
+                    if let Some(value) =
+                        read_line(&mut screen, &mut keys, &msg!("tui-new-probability"), &initial)?
+                    {
+                        match value.trim().parse::<f32>() {
+                            Ok(probability) if (0.0..=1.0).contains(&probability) => {
+                                categories[*index].probability = probability;
+                            }
+                            _ => message = Some(msg!("tui-invalid-probability", value = value)),
+                        }
+                    }
+                }
+            }
+            Key::Char('o') => {
+                if let Some(Row::Category(index)) = rows.get(selected) {
+                    let prompt = msg!("tui-new-color", colors = COLORS.join("/"));
+                    if let Some(value) = read_line(&mut screen, &mut keys, &prompt, "")? {
+                        match crate::parse_color(value.trim()) {
+                            Ok(new_color) => categories[*index].color = new_color,
+                            Err(err) => message = Some(err),
+                        }
+                    }
+                }
+            }
+
+            Key::Char('t') => {
+                if let Some(Row::Task(index)) = rows.get(selected) {
+                    let initial = tasks[*index]
+                        .deadline
+                        .map_or(String::new(), |deadline| deadline.format(FMT).to_string());
+                    if let Some(value) =
+                        read_line(&mut screen, &mut keys, &msg!("tui-new-deadline"), &initial)?
+                    {
+                        if value.trim().is_empty() {
+                            tasks[*index].deadline = None;
+                        } else {
+                            match chrono::NaiveDateTime::parse_from_str(value.trim(), FMT) {
+                                Ok(deadline) => tasks[*index].deadline = Some(deadline),
+                                Err(err) => message = Some(msg!("tui-invalid-deadline", error = err.to_string())),
+                            }
+                        }
+                    }
+                }
+            }
+            Key::Char('u') => {
+                if let Some(Row::Task(index)) = rows.get(selected) {
+                    let initial = tasks[*index]
+                        .duration
+                        .map_or(String::new(), |duration| duration.to_string());
+                    if let Some(value) = read_line(
+                        &mut screen,
+                        &mut keys,
+                        &msg!("tui-new-duration"),
+                        &initial,
+                    )? {
+                        if value.trim().is_empty() {
+                            tasks[*index].duration = None;
+                        } else {
+                            match value.trim().parse() {
+                                Ok(duration) => tasks[*index].duration = Some(duration),
+                                Err(_) => message = Some(msg!("tui-invalid-duration", value = value)),
+                            }
+                        }
+                    }
+                }
+            }
+            Key::Char('c') => {
+                if let Some(Row::Task(index)) = rows.get(selected) {
+                    let prompt = msg!(
+                        "tui-new-task-category",
+                        categories = categories
+                            .iter()
+                            .map(|category| category.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join("/")
+                    );
+                    if let Some(name) = read_line(&mut screen, &mut keys, &prompt, "")? {
+                        if categories.iter().any(|category| category.name == name) {
+                            tasks[*index].category = name;
+                        } else if !name.trim().is_empty() {
+                            message = Some(msg!("tui-no-such-category", name = name));
+                        }
+                    }
+                }
+            }
+
+            Key::Char('d') => match rows.get(selected) {
+                Some(Row::Category(index)) => {
+                    if categories[*index].name == "Unclassified" {
+                        message = Some(msg!("tui-cannot-delete-unclassified"));
+                    } else {
+                        delete_category(categories, tasks, &mut expanded, *index);
+                    }
+                }
+                Some(Row::Task(index)) => {
+                    tasks.remove(*index);
+                }
+                None => {}
+            },
+            Key::Char(' ') | Key::Char('\n') => {
+                if let Some(Row::Task(index)) = rows.get(selected) {
+                    tasks.remove(*index);
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    write!(screen, "{}", termion::cursor::Show)?;
+    screen.flush()
+}
+
+/// The category a new task/row-relative action should apply to: the selected
+/// category row itself, or the category of the selected task row.
+fn category_of(rows: &[Row], selected: usize, categories: &[Category], tasks: &[Task]) -> Option<usize> {
+    match rows.get(selected)? {
+        Row::Category(index) => Some(*index),
+        Row::Task(index) => categories
+            .iter()
+            .position(|category| category.name == tasks[*index].category),
+    }
+}
+
+/// Moves every task in `categories[index]` to "Unclassified" and removes the category.
+///
+/// `categories.remove(index)` shifts every later category's index down by
+/// one, so `expanded` (which tracks expand/collapse state by that same index)
+/// is reindexed to match: the removed index drops out, and every index past
+/// it is decremented.
+fn delete_category(categories: &mut Vec<Category>, tasks: &mut [Task], expanded: &mut HashSet<usize>, index: usize) {
+    let name = categories[index].name.clone();
+    for task in tasks.iter_mut() {
+        if task.category == name {
+            task.category = String::from("Unclassified");
+        }
+    }
+    if !categories.iter().any(|category| category.name == "Unclassified") {
+        categories.push(Category {
+            name: String::from("Unclassified"),
+            probability: 1.00,
+            color: Color::White,
+        });
+    }
+    categories.remove(index);
+
+    *expanded = expanded
+        .drain()
+        .filter(|&i| i != index)
+        .map(|i| if i > index { i - 1 } else { i })
+        .collect();
+}
+
+/// Flattens `categories`/`tasks` into rows, expanding only categories in `expanded`.
+fn build_rows(categories: &[Category], tasks: &[Task], expanded: &HashSet<usize>) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for (category_index, category) in categories.iter().enumerate() {
+        rows.push(Row::Category(category_index));
+        if expanded.contains(&category_index) {
+            for (task_index, task) in tasks.iter().enumerate() {
+                if task.category == category.name {
+                    rows.push(Row::Task(task_index));
+                }
+            }
+        }
+    }
+    rows
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn draw(
+    screen: &mut impl Write,
+    categories: &[Category],
+    tasks: &[Task],
+    expanded: &HashSet<usize>,
+    selected: usize,
+    message: Option<String>,
+) -> io::Result<()> {
+    write!(screen, "{}{}", Goto(1, 1), clear::All)?;
+
+    let rows = build_rows(categories, tasks, expanded);
+    for (row_index, row) in rows.iter().enumerate() {
+        write!(screen, "{}", Goto(1, row_index as u16 + 1))?;
+        if row_index == selected {
+            write!(screen, "{}", style::Invert)?;
+        }
+
+        match row {
+            Row::Category(category_index) => {
+                let category = &categories[*category_index];
+                let marker = if expanded.contains(category_index) { 'v' } else { '>' };
+                write!(
+                    screen,
+                    "{}{} {} ({:.0}%){}",
+                    fg(category.color),
+                    marker,
+                    category.name,
+                    category.probability * 100.0,
+                    style::Reset
+                )?;
+            }
+            Row::Task(task_index) => {
+                let task = &tasks[*task_index];
+                let deadline = task
+                    .deadline
+                    .map_or_else(|| msg!("tui-no-deadline"), |deadline| deadline.format(FMT).to_string());
+                let duration = task
+                    .duration
+                    .map_or(String::new(), |duration| format!(", {} min", duration));
+                write!(screen, "    {} ({}{}){}", task.task, deadline, duration, style::Reset)?;
+            }
+        }
+    }
+
+    let (_, height) = termion::terminal_size()?;
+    let help = msg!("tui-help");
+    write!(
+        screen,
+        "{}{}",
+        Goto(1, height),
+        message.as_deref().unwrap_or(&help)
+    )?;
+
+    screen.flush()
+}
+
+/// Reads one line of input on the bottom status row, Vim-insert style:
+/// `Enter` accepts, `Esc` cancels.
+fn read_line(
+    screen: &mut impl Write,
+    keys: &mut Keys<Stdin>,
+    prompt: &str,
+    initial: &str,
+) -> io::Result<Option<String>> {
+    let mut buffer = String::from(initial);
+    let (_, height) = termion::terminal_size()?;
+
+    loop {
+        write!(
+            screen,
+            "{}{}{}: {}{}",
+            Goto(1, height),
+            clear::CurrentLine,
+            prompt,
+            buffer,
+            termion::cursor::Show
+        )?;
+        screen.flush()?;
+
+        let result = match keys.next() {
+            Some(Ok(Key::Char('\n'))) => Some(Some(buffer.clone())),
+            Some(Ok(Key::Esc)) => Some(None),
+            Some(Ok(Key::Backspace)) => {
+                buffer.pop();
+                None
+            }
+            Some(Ok(Key::Char(character))) => {
+                buffer.push(character);
+                None
+            }
+            Some(Ok(_)) => None,
+            Some(Err(err)) => return Err(err),
+            None => Some(None),
+        };
+
+        if let Some(outcome) = result {
+            write!(screen, "{}", termion::cursor::Hide)?;
+            return Ok(outcome);
+        }
+    }
+}
+
+fn fg(color: Color) -> String {
+    match color {
+        Color::Black => format!("{}", color::Fg(color::Black)),
+        Color::Blue => format!("{}", color::Fg(color::Blue)),
+        Color::Green => format!("{}", color::Fg(color::Green)),
+        Color::Red => format!("{}", color::Fg(color::Red)),
+        Color::Cyan => format!("{}", color::Fg(color::Cyan)),
+        Color::Magenta => format!("{}", color::Fg(color::Magenta)),
+        Color::Yellow => format!("{}", color::Fg(color::Yellow)),
+        _ => format!("{}", color::Fg(color::White)),
+    }
+}